@@ -0,0 +1,148 @@
+//! Per-job artifact directories. Each job gets its own working directory
+//! under the artifacts root; whatever files the script leaves there are
+//! listed in its `JobLog` and downloadable afterwards.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone)]
+pub(crate) struct ArtifactMeta {
+    pub(crate) path: String,
+    pub(crate) size: u64,
+    pub(crate) content_type: String,
+}
+
+pub(crate) fn job_artifact_dir(artifacts_root: &Path, job_id: &str) -> PathBuf {
+    artifacts_root.join(job_id)
+}
+
+pub(crate) fn list_artifacts(job_dir: &Path) -> std::io::Result<Vec<ArtifactMeta>> {
+    let mut artifacts = Vec::new();
+    if job_dir.exists() {
+        collect(job_dir, job_dir, &mut artifacts)?;
+    }
+    Ok(artifacts)
+}
+
+fn collect(root: &Path, dir: &Path, out: &mut Vec<ArtifactMeta>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        // `file_type()` reports the entry itself rather than what it points
+        // to (unlike `path.is_dir()`/`is_file()`, which follow symlinks).
+        // A script could otherwise drop a symlink in its own artifact
+        // directory pointing anywhere readable and have it listed here.
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            continue;
+        }
+        if file_type.is_dir() {
+            collect(root, &path, out)?;
+        } else if file_type.is_file() {
+            let metadata = entry.metadata()?;
+            let relative_path = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .to_string();
+            out.push(ArtifactMeta {
+                path: relative_path,
+                size: metadata.len(),
+                content_type: mime_guess::from_path(&path)
+                    .first_or_octet_stream()
+                    .to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Resolves a requested artifact path under a job's directory, rejecting
+/// traversal outside it.
+pub(crate) fn validate_artifact_path(job_dir: &Path, requested: &str) -> Result<PathBuf, String> {
+    if requested.contains("..") || requested.starts_with('/') {
+        return Err("Invalid artifact path: path traversal detected".to_string());
+    }
+
+    let full_path = job_dir.join(requested);
+
+    if !full_path.starts_with(job_dir) {
+        return Err("Artifact path outside job directory".to_string());
+    }
+
+    if !full_path.is_file() {
+        return Err("Artifact not found".to_string());
+    }
+
+    // The checks above are lexical and run before symlinks are resolved, so
+    // a symlink planted under `job_dir` can still point outside it. Resolve
+    // both paths and re-check containment before handing back a path that's
+    // actually going to be opened and streamed to the caller.
+    let canonical_job_dir = job_dir
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve job directory: {}", e))?;
+    let canonical_path = full_path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve artifact path: {}", e))?;
+    if !canonical_path.starts_with(&canonical_job_dir) {
+        return Err("Artifact path outside job directory".to_string());
+    }
+
+    Ok(canonical_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "gcloud-provision-artifacts-test-{}-{}",
+            name,
+            uuid::Uuid::new_v4()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn accepts_regular_file_inside_job_dir() {
+        let job_dir = temp_dir("ok");
+        fs::write(job_dir.join("output.txt"), "hello").unwrap();
+
+        assert!(validate_artifact_path(&job_dir, "output.txt").is_ok());
+
+        let _ = fs::remove_dir_all(&job_dir);
+    }
+
+    #[test]
+    fn rejects_symlink_escaping_job_dir() {
+        let job_dir = temp_dir("escape");
+        let secret = std::env::temp_dir().join(format!("gcloud-provision-secret-{}", uuid::Uuid::new_v4()));
+        fs::write(&secret, "top secret").unwrap();
+        std::os::unix::fs::symlink(&secret, job_dir.join("escape.txt")).unwrap();
+
+        let result = validate_artifact_path(&job_dir, "escape.txt");
+        assert!(result.is_err(), "symlink escaping job_dir should be rejected");
+
+        let _ = fs::remove_dir_all(&job_dir);
+        let _ = fs::remove_file(&secret);
+    }
+
+    #[test]
+    fn list_artifacts_skips_symlinks() {
+        let job_dir = temp_dir("list");
+        let secret = std::env::temp_dir().join(format!("gcloud-provision-secret-{}", uuid::Uuid::new_v4()));
+        fs::write(&secret, "top secret").unwrap();
+        std::os::unix::fs::symlink(&secret, job_dir.join("link.txt")).unwrap();
+        fs::write(job_dir.join("real.txt"), "hi").unwrap();
+
+        let found = list_artifacts(&job_dir).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, "real.txt");
+
+        let _ = fs::remove_dir_all(&job_dir);
+        let _ = fs::remove_file(&secret);
+    }
+}
@@ -0,0 +1,46 @@
+//! Bearer-token authentication for the provisioning API. `/health` stays
+//! open; everything else is rejected unless `AUTH_SECRET` is unset (local
+//! dev) or the caller presents a matching `Authorization: Bearer <token>`.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::AppState;
+
+pub(crate) async fn require_bearer_token(
+    State(state): State<AppState>,
+    req: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, String)> {
+    let Some(expected) = &state.auth_secret else {
+        // No secret configured: auth is opt-in, so local dev keeps working.
+        return Ok(next.run(req).await);
+    };
+
+    let provided_token = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match provided_token {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => {
+            Ok(next.run(req).await)
+        }
+        _ => Err((
+            StatusCode::UNAUTHORIZED,
+            "Missing or invalid bearer token".to_string(),
+        )),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,298 @@
+//! Lightweight runner agent: registers with a driver, long-polls it for
+//! jobs matching this runner's capability tags, executes the script
+//! locally, and streams its output and final status back over HTTP. Meant
+//! to run on hosts that carry the tooling (gcloud SDK, terraform, ...) a
+//! job needs without making the driver box hold all of it itself.
+
+use std::process::Stdio;
+use std::time::Duration;
+
+use gcloud_provision::process::terminate_process_group;
+use gcloud_provision::wire::{
+    CancelledResponse, CompleteRequest, OutputLineRequest, PendingJobPayload, PollResponse,
+    RegisterRequest,
+};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use uuid::Uuid;
+
+/// How long a single poll is allowed to hang before the runner retries. A
+/// little longer than the driver's own long-poll window so a slow network
+/// doesn't look like a dropped connection.
+const POLL_TIMEOUT: Duration = Duration::from_secs(35);
+
+/// How often a running job checks in with the driver to see if it's been
+/// cancelled (manually, or by the driver's own timeout giving up on us).
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+
+struct RunnerConfig {
+    driver_url: String,
+    runner_id: String,
+    tags: Vec<String>,
+    auth_token: Option<String>,
+}
+
+impl RunnerConfig {
+    fn from_env() -> Result<Self, String> {
+        let driver_url = std::env::var("DRIVER_URL")
+            .map_err(|_| "DRIVER_URL must be set to the driver's base URL".to_string())?
+            .trim_end_matches('/')
+            .to_string();
+        let runner_id =
+            std::env::var("RUNNER_ID").unwrap_or_else(|_| format!("runner-{}", Uuid::new_v4()));
+        let tags = std::env::var("RUNNER_TAGS")
+            .map(|raw| raw.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect())
+            .unwrap_or_default();
+        let auth_token = std::env::var("RUNNER_AUTH_TOKEN").ok();
+
+        Ok(Self {
+            driver_url,
+            runner_id,
+            tags,
+            auth_token,
+        })
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let config = RunnerConfig::from_env()?;
+    let client = reqwest::Client::new();
+
+    register(&client, &config).await?;
+    println!(
+        "Registered as runner {} with tags {:?} against driver {}",
+        config.runner_id, config.tags, config.driver_url
+    );
+
+    loop {
+        match poll(&client, &config).await {
+            Ok(Some(job)) => {
+                let job_id = job.job_id.clone();
+                if let Err(e) = run_job(&client, &config, job).await {
+                    eprintln!("Job {} failed on this runner: {}", job_id, e);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("Poll against driver failed: {}", e);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        }
+    }
+}
+
+async fn register(client: &reqwest::Client, config: &RunnerConfig) -> Result<(), String> {
+    let mut req = client
+        .post(format!("{}/runners/register", config.driver_url))
+        .json(&RegisterRequest {
+            runner_id: config.runner_id.clone(),
+            tags: config.tags.clone(),
+        });
+    if let Some(token) = &config.auth_token {
+        req = req.bearer_auth(token);
+    }
+    req.send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn poll(client: &reqwest::Client, config: &RunnerConfig) -> Result<Option<PendingJobPayload>, String> {
+    let mut req = client
+        .get(format!(
+            "{}/runners/{}/poll",
+            config.driver_url, config.runner_id
+        ))
+        .timeout(POLL_TIMEOUT);
+    if let Some(token) = &config.auth_token {
+        req = req.bearer_auth(token);
+    }
+    let response: PollResponse = req
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response.job)
+}
+
+async fn run_job(
+    client: &reqwest::Client,
+    config: &RunnerConfig,
+    job: PendingJobPayload,
+) -> Result<(), String> {
+    let scratch_dir = std::env::temp_dir().join(format!("gcloud-provision-runner-{}", job.job_id));
+    tokio::fs::create_dir_all(&scratch_dir)
+        .await
+        .map_err(|e| format!("failed to create scratch dir: {}", e))?;
+    let script_path = scratch_dir.join("script.sh");
+    tokio::fs::write(&script_path, &job.script_content)
+        .await
+        .map_err(|e| format!("failed to write script: {}", e))?;
+
+    let mut cmd = Command::new("bash");
+    cmd.arg(&script_path)
+        .args(&job.args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .current_dir(&scratch_dir)
+        .env("ARTIFACT_DIR", &scratch_dir)
+        // Its own process group, so cancellation can signal the whole tree
+        // the script spawns, not just the immediate bash pid.
+        .process_group(0)
+        .kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            report_complete(
+                client,
+                config,
+                &job.job_id,
+                "failed",
+                None,
+                Some(format!("Failed to execute script: {}", e)),
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let pid = child.id();
+    let stdout = child.stdout.take().expect("stdout was piped");
+    let stderr = child.stderr.take().expect("stderr was piped");
+    let mut stdout_lines = BufReader::new(stdout).lines();
+    let mut stderr_lines = BufReader::new(stderr).lines();
+
+    let mut cancel_check = tokio::time::interval(CANCEL_CHECK_INTERVAL);
+    let mut cancelled = false;
+    let mut stdout_done = false;
+    let mut stderr_done = false;
+    while !stdout_done || !stderr_done {
+        tokio::select! {
+            line = stdout_lines.next_line(), if !stdout_done => {
+                match line.map_err(|e| e.to_string())? {
+                    Some(l) => report_output(client, config, &job.job_id, "stdout", l).await?,
+                    None => stdout_done = true,
+                }
+            }
+            line = stderr_lines.next_line(), if !stderr_done => {
+                match line.map_err(|e| e.to_string())? {
+                    Some(l) => report_output(client, config, &job.job_id, "stderr", l).await?,
+                    None => stderr_done = true,
+                }
+            }
+            _ = cancel_check.tick() => {
+                if !cancelled && check_cancelled(client, config, &job.job_id).await? {
+                    cancelled = true;
+                    // SIGTERM the whole process group, give it a grace
+                    // period, then SIGKILL anything still alive — reaches
+                    // grandchildren the script spawned, not just bash itself.
+                    if let Some(pid) = pid {
+                        terminate_process_group(pid).await;
+                    }
+                }
+            }
+        }
+    }
+
+    let exit_status = child.wait().await.map_err(|e| e.to_string())?;
+    let status = if cancelled {
+        "cancelled"
+    } else if exit_status.success() {
+        "completed"
+    } else {
+        "failed"
+    };
+    report_complete(client, config, &job.job_id, status, exit_status.code(), None).await?;
+
+    let _ = tokio::fs::remove_dir_all(&scratch_dir).await;
+    Ok(())
+}
+
+async fn check_cancelled(
+    client: &reqwest::Client,
+    config: &RunnerConfig,
+    job_id: &str,
+) -> Result<bool, String> {
+    let mut req = client.get(format!(
+        "{}/runners/{}/jobs/{}/cancelled",
+        config.driver_url, config.runner_id, job_id
+    ));
+    if let Some(token) = &config.auth_token {
+        req = req.bearer_auth(token);
+    }
+    let response: CancelledResponse = req
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response.cancelled)
+}
+
+async fn report_output(
+    client: &reqwest::Client,
+    config: &RunnerConfig,
+    job_id: &str,
+    stream: &str,
+    line: String,
+) -> Result<(), String> {
+    let mut req = client
+        .post(format!(
+            "{}/runners/{}/jobs/{}/output",
+            config.driver_url, config.runner_id, job_id
+        ))
+        .json(&OutputLineRequest {
+            stream: stream.to_string(),
+            line,
+        });
+    if let Some(token) = &config.auth_token {
+        req = req.bearer_auth(token);
+    }
+    req.send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+async fn report_complete(
+    client: &reqwest::Client,
+    config: &RunnerConfig,
+    job_id: &str,
+    status: &str,
+    exit_code: Option<i32>,
+    error_message: Option<String>,
+) -> Result<(), String> {
+    let mut req = client
+        .post(format!(
+            "{}/runners/{}/jobs/{}/complete",
+            config.driver_url, config.runner_id, job_id
+        ))
+        .json(&CompleteRequest {
+            status: status.to_string(),
+            exit_code,
+            error_message,
+        });
+    if let Some(token) = &config.auth_token {
+        req = req.bearer_auth(token);
+    }
+    req.send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! SQLite-backed persistence for jobs and their output, so job state survives
+//! process restarts instead of living only in an in-memory `HashMap`.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::{JobLog, JobStatus};
+
+#[derive(Clone)]
+pub struct DbCtx {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl DbCtx {
+    pub async fn connect(path: &str) -> rusqlite::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                job_id          TEXT PRIMARY KEY,
+                script_path     TEXT NOT NULL,
+                args            TEXT NOT NULL,
+                status          TEXT NOT NULL,
+                exit_code       INTEGER,
+                error_message   TEXT,
+                runner_id       TEXT,
+                created_at      TEXT NOT NULL,
+                finished_at     TEXT
+             );
+             CREATE TABLE IF NOT EXISTS job_output (
+                job_id TEXT NOT NULL,
+                seq    INTEGER NOT NULL,
+                stream TEXT NOT NULL,
+                line   TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS job_output_job_id_idx ON job_output (job_id);",
+        )?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub async fn create_job(
+        &self,
+        job_id: &str,
+        script_path: &str,
+        args: &[String],
+    ) -> rusqlite::Result<()> {
+        let args_json = serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string());
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT INTO jobs (job_id, script_path, args, status, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                job_id,
+                script_path,
+                args_json,
+                JobStatus::Running.as_db_str(),
+                chrono::Utc::now().to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub async fn append_output(&self, job_id: &str, stream: &str, line: &str) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().await;
+        let next_seq: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(seq), -1) + 1 FROM job_output WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get(0),
+        )?;
+        conn.execute(
+            "INSERT INTO job_output (job_id, seq, stream, line) VALUES (?1, ?2, ?3, ?4)",
+            params![job_id, next_seq, stream, line],
+        )?;
+        Ok(())
+    }
+
+    /// Records which runner picked up a queued job, once one claims it. Local
+    /// jobs are assigned to the built-in `"local"` runner at claim time too,
+    /// so `JobLog::runner_id` always reflects where a job actually ran.
+    pub async fn assign_runner(&self, job_id: &str, runner_id: &str) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE jobs SET runner_id = ?1 WHERE job_id = ?2",
+            params![runner_id, job_id],
+        )?;
+        Ok(())
+    }
+
+    /// Which runner a job is (or was) assigned to, if any. Used to check that
+    /// a runner reporting output/completion for a job is the one it was
+    /// actually handed to, not just any bearer-token holder guessing an id.
+    pub async fn runner_for_job(&self, job_id: &str) -> rusqlite::Result<Option<String>> {
+        let conn = self.conn.lock().await;
+        conn.query_row(
+            "SELECT runner_id FROM jobs WHERE job_id = ?1",
+            params![job_id],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map(|opt| opt.flatten())
+    }
+
+    pub async fn finalize_job(
+        &self,
+        job_id: &str,
+        status: JobStatus,
+        exit_code: Option<i32>,
+        error_message: Option<String>,
+    ) -> rusqlite::Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE jobs SET status = ?1, exit_code = ?2, error_message = ?3, finished_at = ?4
+             WHERE job_id = ?5",
+            params![
+                status.as_db_str(),
+                exit_code,
+                error_message,
+                chrono::Utc::now().to_rfc3339(),
+                job_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub async fn get_job(&self, job_id: &str) -> rusqlite::Result<Option<JobLog>> {
+        let conn = self.conn.lock().await;
+
+        let job_row = conn
+            .query_row(
+                "SELECT status, exit_code, error_message, runner_id FROM jobs WHERE job_id = ?1",
+                params![job_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Option<i32>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
+            )
+            .optional()?;
+
+        let Some((status, exit_code, error_message, runner_id)) = job_row else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn.prepare(
+            "SELECT stream, line FROM job_output WHERE job_id = ?1 ORDER BY seq ASC",
+        )?;
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let rows = stmt.query_map(params![job_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (stream, line) = row?;
+            let buf = if stream == "stdout" { &mut stdout } else { &mut stderr };
+            buf.push_str(&line);
+            buf.push('\n');
+        }
+
+        Ok(Some(JobLog {
+            status: JobStatus::from_db_str(&status),
+            stdout,
+            stderr,
+            exit_code,
+            error_message,
+            runner_id,
+            artifacts: Vec::new(),
+        }))
+    }
+
+    /// A job still marked `running` at startup can't actually be running — the
+    /// process that was driving it just restarted. Mark those as failed, with
+    /// an error message saying the server restarted while they ran.
+    pub async fn reconcile_interrupted_jobs(&self) -> rusqlite::Result<usize> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "UPDATE jobs SET status = ?1, error_message = ?2, finished_at = ?3 WHERE status = ?4",
+            params![
+                JobStatus::Failed.as_db_str(),
+                "Job was still running when the server restarted",
+                chrono::Utc::now().to_rfc3339(),
+                JobStatus::Running.as_db_str(),
+            ],
+        )
+    }
+}
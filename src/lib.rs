@@ -0,0 +1,6 @@
+//! Types and helpers shared between the driver (the axum service in
+//! `main.rs`) and the `runner` binary. Kept in the library target so both
+//! binaries depend on the same definitions instead of drifting apart.
+
+pub mod process;
+pub mod wire;
@@ -1,52 +1,159 @@
 use axum::{
+    body::Body,
     extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, StatusCode},
+    middleware,
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Json},
     routing::{get, post},
     Router,
 };
+use futures_util::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
     fs,
     os::unix::fs::PermissionsExt,
     path::PathBuf,
-    process::{Command, Output},
+    process::Stdio,
     sync::Arc,
 };
-use tokio::sync::Mutex;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::{broadcast, Mutex};
 use tokio::time::{timeout, Duration};
+use tokio_stream::{wrappers::BroadcastStream, StreamExt};
+use tokio_util::{io::ReaderStream, sync::CancellationToken};
 use uuid::Uuid;
 
+mod artifacts;
+mod auth;
+mod db;
+mod notify;
+mod policy;
+mod runner_registry;
+use artifacts::ArtifactMeta;
+use db::DbCtx;
+use gcloud_provision::process::terminate_process_group;
+use gcloud_provision::wire;
+use notify::{NotifyTargets, Notifier};
+use policy::{PolicyContext, PolicyEngine};
+use runner_registry::{claim_job, QueuedJob, RunnerRecord, LOCAL_RUNNER_TAG};
+
 #[derive(Deserialize)]
 struct ScriptExecutionPayload {
     script_path: String,
     #[serde(default)]
     args: Vec<String>,
+    #[serde(default)]
+    notify: NotifyTargets,
+    /// Which runner tag should execute this job; `None` lets any runner
+    /// (including the built-in local one) claim it.
+    #[serde(default)]
+    runner_tag: Option<String>,
 }
 
 #[derive(Clone)]
 struct AppState {
-    logs: Arc<Mutex<HashMap<String, JobLog>>>,
+    db: DbCtx,
+    // Broadcasts each job's stdout/stderr lines (plus a terminal event) to any
+    // `/logs/:job_id/stream` subscribers while the job is running.
+    streams: Arc<Mutex<HashMap<String, broadcast::Sender<LogEvent>>>>,
+    // Jobs currently executing, so `/jobs/:job_id/cancel` can reach them.
+    running: Arc<Mutex<HashMap<String, RunningJob>>>,
+    // Runners that have registered, keyed by the id they registered with.
+    runners: Arc<Mutex<HashMap<String, RunnerRecord>>>,
+    // Jobs waiting for an eligible runner (including the built-in local one)
+    // to claim them.
+    pending: Arc<Mutex<VecDeque<QueuedJob>>>,
+    // `notify` targets for jobs that are still queued or running, keyed by
+    // job id. Consumed (removed) once the job finishes, wherever it ran.
+    notify_targets: Arc<Mutex<HashMap<String, NotifyTargets>>>,
+    notifier: Notifier,
+    policy: Arc<PolicyEngine>,
     allowed_script_dir: PathBuf,
+    artifacts_dir: PathBuf,
+    auth_secret: Option<String>,
+}
+
+struct RunningJob {
+    // `None` for a job dispatched to a remote runner — the driver never
+    // learns its pid, so cancellation/timeout there can only ask the runner
+    // to stop, not signal a process group directly.
+    pid: Option<u32>,
+    cancel: CancellationToken,
 }
 
 #[derive(Serialize, Clone)]
-struct JobLog {
-    status: JobStatus,
-    stdout: String,
-    stderr: String,
-    exit_code: Option<i32>,
-    error_message: Option<String>,
+pub(crate) struct JobLog {
+    pub(crate) status: JobStatus,
+    pub(crate) stdout: String,
+    pub(crate) stderr: String,
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) error_message: Option<String>,
+    /// Which runner executed this job — `"local"` for the built-in
+    /// in-process executor, or the id a remote runner registered with.
+    /// `None` until a runner actually claims the job off the queue.
+    pub(crate) runner_id: Option<String>,
+    pub(crate) artifacts: Vec<ArtifactMeta>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum StreamKind {
+    Stdout,
+    Stderr,
+}
+
+impl StreamKind {
+    fn as_event_name(&self) -> &'static str {
+        match self {
+            StreamKind::Stdout => "stdout",
+            StreamKind::Stderr => "stderr",
+        }
+    }
+}
+
+#[derive(Clone)]
+enum LogEvent {
+    Line { stream: StreamKind, content: String },
+    Finished {
+        status: JobStatus,
+        exit_code: Option<i32>,
+    },
 }
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
-enum JobStatus {
+pub(crate) enum JobStatus {
     Running,
     Completed,
     Failed,
     TimedOut,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub(crate) fn as_db_str(&self) -> &'static str {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+            JobStatus::TimedOut => "timed_out",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub(crate) fn from_db_str(s: &str) -> Self {
+        match s {
+            "completed" => JobStatus::Completed,
+            "failed" => JobStatus::Failed,
+            "timed_out" => JobStatus::TimedOut,
+            "cancelled" => JobStatus::Cancelled,
+            _ => JobStatus::Running,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -55,16 +162,6 @@ struct ProvisionResponse {
     status: JobStatus,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            logs: Arc::new(Mutex::new(HashMap::new())),
-            // Only allow scripts from a specific directory
-            allowed_script_dir: PathBuf::from("./allowed_scripts"),
-        }
-    }
-}
-
 impl Default for JobLog {
     fn default() -> Self {
         Self {
@@ -73,6 +170,8 @@ impl Default for JobLog {
             stderr: String::new(),
             exit_code: None,
             error_message: None,
+            runner_id: None,
+            artifacts: Vec::new(),
         }
     }
 }
@@ -114,41 +213,23 @@ fn validate_script_path(script_path: &str, allowed_dir: &PathBuf) -> Result<Path
     Ok(full_path)
 }
 
-fn scan_script_for_dangerous_patterns(script_content: &str) -> Result<(), String> {
-    let dangerous_patterns = vec![
-        ("rm -rf /", "Dangerous recursive delete"),
-        ("rm -rf /*", "Dangerous recursive delete"),
-        (":(){:|:&};:", "Fork bomb detected"),
-        ("curl.*\\|.*sh", "Piped execution from web"),
-        ("wget.*\\|.*sh", "Piped execution from web"),
-        ("dd if=/dev/", "Direct disk access"),
-        ("mkfs", "Filesystem creation"),
-        ("fdisk", "Disk partitioning"),
-        ("format", "Disk formatting"),
-        ("/etc/passwd", "Access to password file"),
-        ("sudo", "Privilege escalation"),
-        ("su ", "User switching"),
-        ("chmod 777", "Dangerous permission change"),
-        ("chown", "Ownership change"),
-    ];
-
-    for (pattern, description) in dangerous_patterns {
-        if script_content.contains(pattern) {
-            return Err(format!(
-                "Blocked dangerous pattern: {} ({})",
-                pattern, description
-            ));
-        }
-    }
-    Ok(())
-}
-
-async fn validate_script(script_path: &PathBuf) -> Result<(), String> {
+async fn validate_script(
+    script_path: &PathBuf,
+    args: &[String],
+    policy: &PolicyEngine,
+) -> Result<(), String> {
     // Read and validate script content
     let script_content =
         fs::read_to_string(script_path).map_err(|e| format!("Failed to read script: {}", e))?;
 
-    scan_script_for_dangerous_patterns(&script_content)?;
+    let decision = policy.evaluate(&PolicyContext {
+        script_path: &script_path.to_string_lossy(),
+        script_content: &script_content,
+        args,
+    });
+    if !decision.allowed {
+        return Err(decision.reason);
+    }
     Ok(())
 }
 
@@ -158,23 +239,90 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let allowed_dir = PathBuf::from("./allowed_scripts");
     fs::create_dir_all(&allowed_dir)?;
 
+    let artifacts_dir = PathBuf::from("./artifacts");
+    fs::create_dir_all(&artifacts_dir)?;
+
+    let db_path = std::env::var("DB_PATH").unwrap_or_else(|_| "./gcloud_provision.db".to_string());
+    let db = DbCtx::connect(&db_path).await?;
+    let reconciled = db.reconcile_interrupted_jobs().await?;
+    if reconciled > 0 {
+        println!("Marked {} job(s) interrupted by restart as failed", reconciled);
+    }
+
+    let policy_path = std::env::var("POLICY_LUA_PATH").unwrap_or_else(|_| "./policy.lua".to_string());
+    let policy = PolicyEngine::load(&PathBuf::from(policy_path))?;
+
+    // Auth is opt-in: unset AUTH_SECRET to keep local development open.
+    let auth_secret = std::env::var("AUTH_SECRET").ok();
+
     let state = AppState {
+        db,
+        streams: Arc::new(Mutex::new(HashMap::new())),
+        running: Arc::new(Mutex::new(HashMap::new())),
+        runners: Arc::new(Mutex::new(HashMap::new())),
+        pending: Arc::new(Mutex::new(VecDeque::new())),
+        notify_targets: Arc::new(Mutex::new(HashMap::new())),
+        notifier: Notifier::from_env(),
+        policy: Arc::new(policy),
         allowed_script_dir: allowed_dir,
-        ..Default::default()
+        artifacts_dir,
+        auth_secret,
     };
 
-    let app = Router::new()
+    // The built-in "local" runner: claims queued jobs itself so deployments
+    // that never register a remote runner keep working exactly as before.
+    tokio::spawn(local_runner_loop(state.clone()));
+    // Gives up on jobs nobody ever claims (e.g. a runner_tag with no
+    // matching runner) instead of leaving them stuck "running" forever.
+    tokio::spawn(pending_reaper(state.clone()));
+
+    let protected_routes = Router::new()
         .route("/provision", post(provision))
         .route("/logs/:job_id", get(get_logs))
+        .route("/logs/:job_id/stream", get(stream_logs))
+        .route("/jobs/:job_id/cancel", post(cancel_job))
+        .route("/jobs/:job_id/artifacts", get(list_artifacts_handler))
+        .route("/jobs/:job_id/artifacts/*path", get(download_artifact))
+        .route("/runners/register", post(register_runner))
+        .route("/runners/:runner_id/poll", get(poll_for_job))
+        .route(
+            "/runners/:runner_id/jobs/:job_id/cancelled",
+            get(poll_job_cancelled),
+        )
+        .route("/runners/:runner_id/jobs/:job_id/output", post(report_output))
+        .route(
+            "/runners/:runner_id/jobs/:job_id/complete",
+            post(report_complete),
+        )
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_bearer_token,
+        ));
+
+    let app = protected_routes
         .route("/health", get(health_check))
         .with_state(state);
 
     let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
     let addr = format!("0.0.0.0:{}", port);
-    println!("Listening on {}", addr);
 
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    let tls_cert_path = std::env::var("TLS_CERT_PATH").ok();
+    let tls_key_path = std::env::var("TLS_KEY_PATH").ok();
+
+    match (tls_cert_path, tls_key_path) {
+        (Some(cert_path), Some(key_path)) => {
+            let tls_config = axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_path, key_path).await?;
+            println!("Listening on {} (TLS)", addr);
+            axum_server::bind_rustls(addr.parse()?, tls_config)
+                .serve(app.into_make_service())
+                .await?;
+        }
+        _ => {
+            println!("Listening on {}", addr);
+            let listener = tokio::net::TcpListener::bind(&addr).await?;
+            axum::serve(listener, app).await?;
+        }
+    }
 
     Ok(())
 }
@@ -189,76 +337,268 @@ async fn provision(
         Err(e) => return Err((StatusCode::BAD_REQUEST, e)),
     };
 
-    // Validate script content
-    if let Err(e) = validate_script(&script_path).await {
+    let args = payload.args;
+    let notify_targets = payload.notify;
+
+    // Validate script content against the configured policy
+    if let Err(e) = validate_script(&script_path, &args, &state.policy).await {
         return Err((StatusCode::BAD_REQUEST, e));
     }
 
     let job_id = Uuid::new_v4().to_string();
 
-    // Initialize job log
+    if let Err(e) = state.db.create_job(&job_id, &payload.script_path, &args).await {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to record job: {}", e),
+        ));
+    }
+
+    let job_dir = artifacts::job_artifact_dir(&state.artifacts_dir, &job_id);
+    if let Err(e) = fs::create_dir_all(&job_dir) {
+        return Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create artifact directory: {}", e),
+        ));
+    }
+
+    // Broadcast channel for `/logs/:job_id/stream` subscribers, created
+    // before the job is queued so whichever runner claims it always has one
+    // to send to. It is removed once the job finishes, so a caller that
+    // subscribes after that gets a 404.
+    let (tx, _rx) = broadcast::channel::<LogEvent>(1024);
     {
-        let mut logs = state.logs.lock().await;
-        logs.insert(job_id.clone(), JobLog::default());
+        let mut streams = state.streams.lock().await;
+        streams.insert(job_id.clone(), tx.clone());
     }
+    state
+        .notify_targets
+        .lock()
+        .await
+        .insert(job_id.clone(), notify_targets);
 
-    let state_clone = state.clone();
-    let job_id_clone = job_id.clone();
-    let args = payload.args;
+    state.pending.lock().await.push_back(QueuedJob {
+        job_id: job_id.clone(),
+        script_path,
+        args,
+        required_tag: payload.runner_tag,
+        queued_at: std::time::Instant::now(),
+    });
 
-    tokio::spawn(async move {
-        let mut job_log = JobLog::default();
-
-        // Build command with arguments
-        let mut cmd = Command::new("bash");
-        cmd.arg(&script_path);
-        for arg in &args {
-            // Basic validation of arguments
-            if arg.contains("..") || arg.contains(';') || arg.contains('|') {
-                job_log.status = JobStatus::Failed;
-                job_log.error_message = Some("Invalid argument detected".to_string());
-                state_clone.logs.lock().await.insert(job_id_clone, job_log);
-                return;
+    Ok(Json(ProvisionResponse {
+        job_id,
+        status: JobStatus::Running,
+    }))
+}
+
+/// Background task for the built-in `"local"` runner: claims jobs tagged
+/// for it (or untagged) off the pending queue and executes them in-process,
+/// exactly as `provision` used to do directly before the driver/runner
+/// split.
+async fn local_runner_loop(state: AppState) {
+    loop {
+        let claimed = {
+            let mut pending = state.pending.lock().await;
+            claim_job(&mut pending, &[LOCAL_RUNNER_TAG.to_string()])
+        };
+        match claimed {
+            Some(job) => {
+                tokio::spawn(run_local_job(state.clone(), job));
             }
-            cmd.arg(arg);
+            None => tokio::time::sleep(Duration::from_millis(200)).await,
         }
+    }
+}
 
-        // Use spawn_blocking to run the synchronous command in a blocking thread pool
-        let cmd_future = tokio::task::spawn_blocking(move || cmd.output());
+/// Runs a claimed job's script as a child process and reports its output
+/// and final status the same way a remote runner would, just without the
+/// network hop: straight into `AppState` instead of over HTTP.
+async fn run_local_job(state: AppState, job: QueuedJob) {
+    let Some(tx) = state.streams.lock().await.get(&job.job_id).cloned() else {
+        return;
+    };
+    let _ = state.db.assign_runner(&job.job_id, LOCAL_RUNNER_TAG).await;
 
-        match timeout(Duration::from_secs(300), cmd_future).await {
-            Ok(Ok(Ok(output))) => {
-                job_log.status = if output.status.success() {
-                    JobStatus::Completed
-                } else {
-                    JobStatus::Failed
-                };
-                job_log.stdout = String::from_utf8_lossy(&output.stdout).to_string();
-                job_log.stderr = String::from_utf8_lossy(&output.stderr).to_string();
-                job_log.exit_code = output.status.code();
-            }
-            Ok(Ok(Err(e))) => {
-                job_log.status = JobStatus::Failed;
-                job_log.error_message = Some(format!("Failed to execute script: {}", e));
+    let job_id = job.job_id;
+    let job_dir = artifacts::job_artifact_dir(&state.artifacts_dir, &job_id);
+
+    // Build command with arguments
+    let mut cmd = Command::new("bash");
+    cmd.arg(&job.script_path);
+    for arg in &job.args {
+        // Basic validation of arguments
+        if arg.contains("..") || arg.contains(';') || arg.contains('|') {
+            let _ = tx.send(LogEvent::Finished {
+                status: JobStatus::Failed,
+                exit_code: None,
+            });
+            let _ = state
+                .db
+                .finalize_job(
+                    &job_id,
+                    JobStatus::Failed,
+                    None,
+                    Some("Invalid argument detected".to_string()),
+                )
+                .await;
+            state.streams.lock().await.remove(&job_id);
+            let notify_targets = state.notify_targets.lock().await.remove(&job_id).unwrap_or_default();
+            state.notifier.notify(&job_id, &JobStatus::Failed, None, "", "", &notify_targets).await;
+            return;
+        }
+        cmd.arg(arg);
+    }
+    cmd.stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .current_dir(&job_dir)
+        .env("ARTIFACT_DIR", &job_dir)
+        // Its own process group, so cancellation/timeout can signal the
+        // whole tree the script spawns, not just the immediate bash pid.
+        .process_group(0)
+        .kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let error_message = format!("Failed to execute script: {}", e);
+            let _ = tx.send(LogEvent::Finished {
+                status: JobStatus::Failed,
+                exit_code: None,
+            });
+            let _ = state
+                .db
+                .finalize_job(&job_id, JobStatus::Failed, None, Some(error_message))
+                .await;
+            state.streams.lock().await.remove(&job_id);
+            let notify_targets = state.notify_targets.lock().await.remove(&job_id).unwrap_or_default();
+            state.notifier.notify(&job_id, &JobStatus::Failed, None, "", "", &notify_targets).await;
+            return;
+        }
+    };
+
+    let cancel_token = CancellationToken::new();
+    // Separate from `cancel_token`: fires once this job finishes on its own
+    // so the watcher task below can stop waiting instead of sitting on
+    // `cancel_token.cancelled()` forever for every job that's never
+    // cancelled.
+    let done_token = CancellationToken::new();
+    if let Some(pid) = child.id() {
+        state.running.lock().await.insert(
+            job_id.clone(),
+            RunningJob {
+                pid: Some(pid),
+                cancel: cancel_token.clone(),
+            },
+        );
+        let watcher_cancel = cancel_token.clone();
+        let watcher_done = done_token.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = watcher_cancel.cancelled() => {
+                    terminate_process_group(pid).await;
+                }
+                _ = watcher_done.cancelled() => {}
             }
-            Ok(Err(_)) => {
-                job_log.status = JobStatus::Failed;
-                job_log.error_message = Some("Failed to spawn blocking task".to_string());
+        });
+    }
+
+    let pid = child.id();
+    let stdout = child.stdout.take().expect("stdout was piped");
+    let stderr = child.stderr.take().expect("stderr was piped");
+    let mut stdout_lines = BufReader::new(stdout).lines();
+    let mut stderr_lines = BufReader::new(stderr).lines();
+
+    // Bounded tails kept for the completion notification payload.
+    const TAIL_LINES: usize = 50;
+    let mut stdout_tail: VecDeque<String> = VecDeque::new();
+    let mut stderr_tail: VecDeque<String> = VecDeque::new();
+
+    let run = async {
+        let mut stdout_done = false;
+        let mut stderr_done = false;
+        while !stdout_done || !stderr_done {
+            tokio::select! {
+                line = stdout_lines.next_line(), if !stdout_done => {
+                    match line? {
+                        Some(l) => {
+                            let _ = state.db.append_output(&job_id, "stdout", &l).await;
+                            stdout_tail.push_back(l.clone());
+                            if stdout_tail.len() > TAIL_LINES { stdout_tail.pop_front(); }
+                            let _ = tx.send(LogEvent::Line { stream: StreamKind::Stdout, content: l });
+                        }
+                        None => stdout_done = true,
+                    }
+                }
+                line = stderr_lines.next_line(), if !stderr_done => {
+                    match line? {
+                        Some(l) => {
+                            let _ = state.db.append_output(&job_id, "stderr", &l).await;
+                            stderr_tail.push_back(l.clone());
+                            if stderr_tail.len() > TAIL_LINES { stderr_tail.pop_front(); }
+                            let _ = tx.send(LogEvent::Line { stream: StreamKind::Stderr, content: l });
+                        }
+                        None => stderr_done = true,
+                    }
+                }
             }
-            Err(_) => {
-                job_log.status = JobStatus::TimedOut;
-                job_log.error_message =
-                    Some("Script execution timed out after 5 minutes".to_string());
+        }
+
+        child.wait().await
+    };
+
+    let (status, exit_code, error_message) = match timeout(Duration::from_secs(300), run).await {
+        Ok(Ok(exit_status)) if cancel_token.is_cancelled() => (
+            JobStatus::Cancelled,
+            exit_status.code(),
+            Some("Job was cancelled".to_string()),
+        ),
+        Ok(Ok(exit_status)) => {
+            let status = if exit_status.success() {
+                JobStatus::Completed
+            } else {
+                JobStatus::Failed
+            };
+            (status, exit_status.code(), None)
+        }
+        Ok(Err(e)) => (
+            JobStatus::Failed,
+            None,
+            Some(format!("Failed to execute script: {}", e)),
+        ),
+        Err(_) => {
+            // The `kill_on_drop` flag above cleans up the immediate child;
+            // also reach the rest of the process group it may have spawned.
+            if let Some(pid) = pid {
+                terminate_process_group(pid).await;
             }
+            (
+                JobStatus::TimedOut,
+                None,
+                Some("Script execution timed out after 5 minutes".to_string()),
+            )
         }
+    };
 
-        state_clone.logs.lock().await.insert(job_id_clone, job_log);
+    done_token.cancel();
+    state.running.lock().await.remove(&job_id);
+    let _ = tx.send(LogEvent::Finished {
+        status: status.clone(),
+        exit_code,
     });
+    state.streams.lock().await.remove(&job_id);
+    let _ = state
+        .db
+        .finalize_job(&job_id, status.clone(), exit_code, error_message)
+        .await;
 
-    Ok(Json(ProvisionResponse {
-        job_id,
-        status: JobStatus::Running,
-    }))
+    let stdout_tail = Vec::from(stdout_tail).join("\n");
+    let stderr_tail = Vec::from(stderr_tail).join("\n");
+    let notify_targets = state.notify_targets.lock().await.remove(&job_id).unwrap_or_default();
+    state
+        .notifier
+        .notify(&job_id, &status, exit_code, &stdout_tail, &stderr_tail, &notify_targets)
+        .await;
 }
 
 async fn get_logs(
@@ -270,12 +610,431 @@ async fn get_logs(
         return Err((StatusCode::BAD_REQUEST, "Invalid job ID format".to_string()));
     }
 
-    let logs = state.logs.lock().await;
-    if let Some(log) = logs.get(&job_id) {
-        Ok(Json(log.clone()))
+    match state.db.get_job(&job_id).await {
+        Ok(Some(mut log)) => {
+            let job_dir = artifacts::job_artifact_dir(&state.artifacts_dir, &job_id);
+            log.artifacts = artifacts::list_artifacts(&job_dir).unwrap_or_default();
+            Ok(Json(log))
+        }
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Job not found".to_string())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to load job: {}", e),
+        )),
+    }
+}
+
+async fn stream_logs(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    // Validate job_id format
+    if Uuid::parse_str(&job_id).is_err() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid job ID format".to_string()));
+    }
+
+    let rx = {
+        let streams = state.streams.lock().await;
+        match streams.get(&job_id) {
+            Some(tx) => tx.subscribe(),
+            None => return Err((StatusCode::NOT_FOUND, "Job not found".to_string())),
+        }
+    };
+
+    let stream = BroadcastStream::new(rx).filter_map(|event| match event {
+        Ok(LogEvent::Line { stream, content }) => {
+            Some(Ok(Event::default().event(stream.as_event_name()).data(content)))
+        }
+        Ok(LogEvent::Finished { status, exit_code }) => {
+            let payload = serde_json::json!({ "status": status, "exit_code": exit_code });
+            Some(Ok(Event::default().event("done").data(payload.to_string())))
+        }
+        // A lagged receiver just skips the events it missed; the next tick resumes streaming.
+        Err(_) => None,
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn cancel_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    // Validate job_id format
+    if Uuid::parse_str(&job_id).is_err() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid job ID format".to_string()));
+    }
+
+    {
+        let running = state.running.lock().await;
+        if let Some(job) = running.get(&job_id) {
+            job.cancel.cancel();
+            return Ok(Json(serde_json::json!({
+                "job_id": job_id,
+                "pid": job.pid,
+                "status": "cancelling",
+            })));
+        }
+    }
+
+    // Not claimed by any runner yet: it may still be sitting in the pending
+    // queue, e.g. waiting for a runner tag nobody currently services. Those
+    // jobs have no `running` entry at all, so without this they could never
+    // be cancelled.
+    let was_queued = {
+        let mut pending = state.pending.lock().await;
+        match pending.iter().position(|j| j.job_id == job_id) {
+            Some(pos) => {
+                pending.remove(pos);
+                true
+            }
+            None => false,
+        }
+    };
+    if was_queued {
+        finalize_unclaimed_job(&state, &job_id, JobStatus::Cancelled, "Job was cancelled while queued").await;
+        return Ok(Json(serde_json::json!({
+            "job_id": job_id,
+            "pid": serde_json::Value::Null,
+            "status": "cancelled",
+        })));
+    }
+
+    Err((
+        StatusCode::NOT_FOUND,
+        "Job not found or already finished".to_string(),
+    ))
+}
+
+/// Finalizes a job that never made it to `running` — cancelled or timed out
+/// while still waiting in the pending queue for an eligible runner.
+async fn finalize_unclaimed_job(state: &AppState, job_id: &str, status: JobStatus, error_message: &str) {
+    let _ = state
+        .db
+        .finalize_job(job_id, status.clone(), None, Some(error_message.to_string()))
+        .await;
+    if let Some(tx) = state.streams.lock().await.remove(job_id) {
+        let _ = tx.send(LogEvent::Finished {
+            status: status.clone(),
+            exit_code: None,
+        });
+    }
+    let notify_targets = state.notify_targets.lock().await.remove(job_id).unwrap_or_default();
+    state.notifier.notify(job_id, &status, None, "", "", &notify_targets).await;
+}
+
+/// Background reaper for jobs that sit in the pending queue too long without
+/// any runner claiming them — the same 300s budget `run_local_job` and
+/// `remote_job_watchdog` enforce once a job is actually executing, applied
+/// here to the period before a runner ever picks it up.
+async fn pending_reaper(state: AppState) {
+    const PENDING_TIMEOUT: Duration = Duration::from_secs(300);
+    loop {
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        let timed_out: Vec<String> = {
+            let mut pending = state.pending.lock().await;
+            let mut expired = Vec::new();
+            pending.retain(|job| {
+                if job.queued_at.elapsed() > PENDING_TIMEOUT {
+                    expired.push(job.job_id.clone());
+                    false
+                } else {
+                    true
+                }
+            });
+            expired
+        };
+        for job_id in timed_out {
+            finalize_unclaimed_job(
+                &state,
+                &job_id,
+                JobStatus::TimedOut,
+                "No runner claimed this job within 5 minutes",
+            )
+            .await;
+        }
+    }
+}
+
+async fn list_artifacts_handler(
+    State(state): State<AppState>,
+    Path(job_id): Path<String>,
+) -> Result<Json<Vec<ArtifactMeta>>, (StatusCode, String)> {
+    // Validate job_id format
+    if Uuid::parse_str(&job_id).is_err() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid job ID format".to_string()));
+    }
+
+    let job_dir = artifacts::job_artifact_dir(&state.artifacts_dir, &job_id);
+    artifacts::list_artifacts(&job_dir).map(Json).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to list artifacts: {}", e),
+        )
+    })
+}
+
+async fn download_artifact(
+    State(state): State<AppState>,
+    Path((job_id, artifact_path)): Path<(String, String)>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // Validate job_id format
+    if Uuid::parse_str(&job_id).is_err() {
+        return Err((StatusCode::BAD_REQUEST, "Invalid job ID format".to_string()));
+    }
+
+    let job_dir = artifacts::job_artifact_dir(&state.artifacts_dir, &job_id);
+    let full_path = artifacts::validate_artifact_path(&job_dir, &artifact_path)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let content_type = mime_guess::from_path(&full_path)
+        .first_or_octet_stream()
+        .to_string();
+    let file = tokio::fs::File::open(&full_path)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("Failed to open artifact: {}", e)))?;
+    let body = Body::from_stream(ReaderStream::new(file));
+
+    Ok(([(header::CONTENT_TYPE, content_type)], body))
+}
+
+/// A remote runner calls this once at startup to announce itself and the
+/// capability tags it's willing to claim jobs for. Re-registering (e.g.
+/// after a reconnect) just overwrites the previous tag set.
+async fn register_runner(
+    State(state): State<AppState>,
+    Json(req): Json<wire::RegisterRequest>,
+) -> Json<serde_json::Value> {
+    state
+        .runners
+        .lock()
+        .await
+        .insert(req.runner_id.clone(), RunnerRecord { tags: req.tags });
+    Json(serde_json::json!({ "ok": true }))
+}
+
+/// Long-polled by a registered runner. Returns the next pending job that
+/// matches one of its tags, waiting briefly for one to show up rather than
+/// forcing the runner to busy-poll.
+async fn poll_for_job(
+    State(state): State<AppState>,
+    Path(runner_id): Path<String>,
+) -> Result<Json<wire::PollResponse>, (StatusCode, String)> {
+    let tags = {
+        let runners = state.runners.lock().await;
+        match runners.get(&runner_id) {
+            Some(runner) => runner.tags.clone(),
+            None => return Err((StatusCode::NOT_FOUND, "Runner is not registered".to_string())),
+        }
+    };
+
+    const LONG_POLL_WINDOW: Duration = Duration::from_secs(25);
+    let deadline = tokio::time::Instant::now() + LONG_POLL_WINDOW;
+    loop {
+        let claimed = {
+            let mut pending = state.pending.lock().await;
+            claim_job(&mut pending, &tags)
+        };
+
+        if let Some(job) = claimed {
+            let script_content = fs::read_to_string(&job.script_path).map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Failed to read script for runner: {}", e),
+                )
+            })?;
+            let _ = state.db.assign_runner(&job.job_id, &runner_id).await;
+
+            // Track this job the same way a locally-run one is tracked, so
+            // `/jobs/:job_id/cancel` can reach it and a wedged runner that
+            // never reports back doesn't leave it "running" forever.
+            let cancel_token = CancellationToken::new();
+            state.running.lock().await.insert(
+                job.job_id.clone(),
+                RunningJob {
+                    pid: None,
+                    cancel: cancel_token.clone(),
+                },
+            );
+            tokio::spawn(remote_job_watchdog(state.clone(), job.job_id.clone(), cancel_token));
+
+            return Ok(Json(wire::PollResponse {
+                job: Some(wire::PendingJobPayload {
+                    job_id: job.job_id,
+                    script_content,
+                    args: job.args,
+                }),
+            }));
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(Json(wire::PollResponse { job: None }));
+        }
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+}
+
+/// Watches a job handed to a remote runner for the same 300s budget
+/// `run_local_job` enforces on itself. If the runner hasn't reported
+/// completion (or the job was cancelled and `report_complete` already
+/// stopped watching) by then, the driver gives up on it: marks it timed
+/// out and notifies, rather than leaving it "running" forever because a
+/// runner wedged or crashed without telling us.
+async fn remote_job_watchdog(state: AppState, job_id: String, cancel_token: CancellationToken) {
+    tokio::select! {
+        _ = tokio::time::sleep(Duration::from_secs(300)) => {
+            // Ask the runner to stop (it learns this via the `/cancelled`
+            // poll below) and finalize the job ourselves so it doesn't sit
+            // as "running" indefinitely.
+            cancel_token.cancel();
+            state.running.lock().await.remove(&job_id);
+            if let Some(tx) = state.streams.lock().await.remove(&job_id) {
+                let _ = tx.send(LogEvent::Finished {
+                    status: JobStatus::TimedOut,
+                    exit_code: None,
+                });
+            }
+            let _ = state
+                .db
+                .finalize_job(
+                    &job_id,
+                    JobStatus::TimedOut,
+                    None,
+                    Some("Script execution timed out after 5 minutes".to_string()),
+                )
+                .await;
+            let notify_targets = state.notify_targets.lock().await.remove(&job_id).unwrap_or_default();
+            state
+                .notifier
+                .notify(&job_id, &JobStatus::TimedOut, None, "", "", &notify_targets)
+                .await;
+        }
+        _ = cancel_token.cancelled() => {
+            // Either `report_complete` already finalized the job and cancelled
+            // the token to let us stop watching, or a caller hit
+            // `/jobs/:job_id/cancel` and the runner is expected to notice via
+            // `/cancelled` and report completion itself.
+        }
+    }
+}
+
+/// Checks that `runner_id` is the runner a job was actually assigned to,
+/// so one authenticated caller can't report output or completion for a
+/// job dispatched to someone else.
+async fn authorize_runner_for_job(
+    state: &AppState,
+    runner_id: &str,
+    job_id: &str,
+) -> Result<(), (StatusCode, String)> {
+    match state.db.runner_for_job(job_id).await {
+        Ok(Some(assigned)) if assigned == runner_id => Ok(()),
+        Ok(Some(_)) => Err((
+            StatusCode::FORBIDDEN,
+            "Job is assigned to a different runner".to_string(),
+        )),
+        Ok(None) => Err((StatusCode::NOT_FOUND, "Job not found".to_string())),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to verify runner assignment: {}", e),
+        )),
+    }
+}
+
+/// Polled by a runner while a job is executing to learn whether the driver
+/// wants it stopped (manual cancellation, or the driver's own timeout
+/// giving up on a wedged job).
+async fn poll_job_cancelled(
+    State(state): State<AppState>,
+    Path((runner_id, job_id)): Path<(String, String)>,
+) -> Result<Json<wire::CancelledResponse>, (StatusCode, String)> {
+    authorize_runner_for_job(&state, &runner_id, &job_id).await?;
+    // A job assigned to this runner but no longer tracked in `running` has
+    // already been finalized by the driver (e.g. `remote_job_watchdog` timed
+    // it out), so the runner should stop it too.
+    let cancelled = state
+        .running
+        .lock()
+        .await
+        .get(&job_id)
+        .map(|job| job.cancel.is_cancelled())
+        .unwrap_or(true);
+    Ok(Json(wire::CancelledResponse { cancelled }))
+}
+
+/// A runner relays a stdout/stderr line as it produces it, so `/logs/:job_id/stream`
+/// subscribers see remote output with the same latency as local execution.
+async fn report_output(
+    State(state): State<AppState>,
+    Path((runner_id, job_id)): Path<(String, String)>,
+    Json(req): Json<wire::OutputLineRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    authorize_runner_for_job(&state, &runner_id, &job_id).await?;
+
+    let stream = if req.stream == "stderr" {
+        StreamKind::Stderr
     } else {
-        Err((StatusCode::NOT_FOUND, "Job not found".to_string()))
+        StreamKind::Stdout
+    };
+
+    state
+        .db
+        .append_output(&job_id, stream.as_event_name(), &req.line)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to record output: {}", e),
+            )
+        })?;
+
+    if let Some(tx) = state.streams.lock().await.get(&job_id) {
+        let _ = tx.send(LogEvent::Line { stream, content: req.line });
     }
+
+    Ok(StatusCode::OK)
+}
+
+/// A runner reports a job's final status once its script exits.
+async fn report_complete(
+    State(state): State<AppState>,
+    Path((runner_id, job_id)): Path<(String, String)>,
+    Json(req): Json<wire::CompleteRequest>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    authorize_runner_for_job(&state, &runner_id, &job_id).await?;
+
+    let status = JobStatus::from_db_str(&req.status);
+
+    state
+        .db
+        .finalize_job(&job_id, status.clone(), req.exit_code, req.error_message.clone())
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to finalize job: {}", e),
+            )
+        })?;
+
+    // Let the driver-side timeout watchdog (if any) know the job is done so
+    // it stops watching instead of firing 300s after dispatch regardless.
+    if let Some(job) = state.running.lock().await.remove(&job_id) {
+        job.cancel.cancel();
+    }
+
+    if let Some(tx) = state.streams.lock().await.remove(&job_id) {
+        let _ = tx.send(LogEvent::Finished {
+            status: status.clone(),
+            exit_code: req.exit_code,
+        });
+    }
+
+    let notify_targets = state.notify_targets.lock().await.remove(&job_id).unwrap_or_default();
+    state
+        .notifier
+        .notify(&job_id, &status, req.exit_code, "", "", &notify_targets)
+        .await;
+
+    Ok(StatusCode::OK)
 }
 
 async fn health_check() -> Json<serde_json::Value> {
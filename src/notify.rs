@@ -0,0 +1,269 @@
+//! Completion notifications for finished jobs — an HTTP webhook and, if SMTP
+//! is configured, email. Delivery failures are logged but never change the
+//! job's recorded status.
+
+use serde::{Deserialize, Serialize};
+
+use crate::JobStatus;
+
+#[derive(Deserialize, Clone, Default)]
+pub(crate) struct NotifyTargets {
+    #[serde(default)]
+    pub(crate) webhook_url: Option<String>,
+    #[serde(default)]
+    pub(crate) email: bool,
+}
+
+#[derive(Clone)]
+struct SmtpConfig {
+    relay: String,
+    username: String,
+    password: String,
+    from: String,
+    to: String,
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Notifier {
+    smtp: Option<SmtpConfig>,
+}
+
+#[derive(Serialize)]
+struct WebhookPayload<'a> {
+    job_id: &'a str,
+    status: &'a str,
+    exit_code: Option<i32>,
+    stdout_tail: &'a str,
+    stderr_tail: &'a str,
+}
+
+impl Notifier {
+    /// Picks up SMTP config from the environment; webhooks need no startup
+    /// config since the target URL travels with each job's `notify` payload.
+    pub(crate) fn from_env() -> Self {
+        let smtp = match (
+            std::env::var("SMTP_RELAY"),
+            std::env::var("SMTP_USERNAME"),
+            std::env::var("SMTP_PASSWORD"),
+            std::env::var("SMTP_FROM"),
+            std::env::var("SMTP_TO"),
+        ) {
+            (Ok(relay), Ok(username), Ok(password), Ok(from), Ok(to)) => Some(SmtpConfig {
+                relay,
+                username,
+                password,
+                from,
+                to,
+            }),
+            _ => None,
+        };
+        Self { smtp }
+    }
+
+    pub(crate) async fn notify(
+        &self,
+        job_id: &str,
+        status: &JobStatus,
+        exit_code: Option<i32>,
+        stdout_tail: &str,
+        stderr_tail: &str,
+        targets: &NotifyTargets,
+    ) {
+        if let Some(webhook_url) = &targets.webhook_url {
+            if let Err(e) = self
+                .send_webhook(webhook_url, job_id, status, exit_code, stdout_tail, stderr_tail)
+                .await
+            {
+                eprintln!("Webhook notification for job {} failed: {}", job_id, e);
+            }
+        }
+
+        if targets.email {
+            match &self.smtp {
+                Some(smtp) => {
+                    if let Err(e) = self
+                        .send_email(smtp, job_id, status, exit_code, stdout_tail, stderr_tail)
+                        .await
+                    {
+                        eprintln!("Email notification for job {} failed: {}", job_id, e);
+                    }
+                }
+                None => {
+                    eprintln!(
+                        "Email notification requested for job {} but SMTP is not configured",
+                        job_id
+                    );
+                }
+            }
+        }
+    }
+
+    async fn send_webhook(
+        &self,
+        url: &str,
+        job_id: &str,
+        status: &JobStatus,
+        exit_code: Option<i32>,
+        stdout_tail: &str,
+        stderr_tail: &str,
+    ) -> Result<(), String> {
+        let (parsed_url, pinned_addr) = validate_webhook_url(url).await?;
+        let host = parsed_url
+            .host_str()
+            .ok_or_else(|| "webhook URL has no host".to_string())?;
+
+        // Dial exactly the address we just validated instead of letting
+        // reqwest re-resolve the host itself: a second, independent DNS
+        // lookup at connect time could return something different (and
+        // disallowed) than the one we checked above.
+        let client = reqwest::Client::builder()
+            .resolve(host, pinned_addr)
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let payload = WebhookPayload {
+            job_id,
+            status: status.as_db_str(),
+            exit_code,
+            stdout_tail,
+            stderr_tail,
+        };
+        client
+            .post(parsed_url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    async fn send_email(
+        &self,
+        smtp: &SmtpConfig,
+        job_id: &str,
+        status: &JobStatus,
+        exit_code: Option<i32>,
+        stdout_tail: &str,
+        stderr_tail: &str,
+    ) -> Result<(), String> {
+        use lettre::transport::smtp::authentication::Credentials;
+        use lettre::{Message, SmtpTransport, Transport};
+
+        let body = format!(
+            "Job {job_id} finished with status {status} (exit code: {exit_code:?})\n\n\
+             --- stdout (tail) ---\n{stdout_tail}\n\n--- stderr (tail) ---\n{stderr_tail}",
+            status = status.as_db_str(),
+        );
+        let email = Message::builder()
+            .from(smtp.from.parse().map_err(|e| format!("invalid from address: {}", e))?)
+            .to(smtp.to.parse().map_err(|e| format!("invalid to address: {}", e))?)
+            .subject(format!("Provisioning job {} {}", job_id, status.as_db_str()))
+            .body(body)
+            .map_err(|e| e.to_string())?;
+
+        let mailer = SmtpTransport::relay(&smtp.relay)
+            .map_err(|e| e.to_string())?
+            .credentials(Credentials::new(smtp.username.clone(), smtp.password.clone()))
+            .build();
+
+        tokio::task::spawn_blocking(move || mailer.send(&email))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
+
+/// `webhook_url` comes straight from whoever calls `/provision`, so without
+/// this check it's an SSRF primitive: a caller could point it at the GCE
+/// metadata endpoint (169.254.169.254) or any other service only reachable
+/// from inside this host's network. Resolves the host, rejects it if any
+/// resolved address is loopback, private, link-local, unspecified or
+/// multicast (see `is_disallowed_target`), and returns the parsed URL
+/// alongside the address the caller should actually dial — resolving again
+/// at connect time would let a low-TTL DNS answer swap in a disallowed
+/// address after this check already passed.
+async fn validate_webhook_url(url: &str) -> Result<(reqwest::Url, std::net::SocketAddr), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("invalid webhook URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err("webhook URL must be http or https".to_string());
+    }
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "webhook URL has no host".to_string())?;
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| format!("failed to resolve webhook host: {}", e))?
+        .collect::<Vec<_>>();
+    let Some(&pinned) = addrs.first() else {
+        return Err("webhook host did not resolve to any address".to_string());
+    };
+    for addr in &addrs {
+        if is_disallowed_target(addr.ip()) {
+            return Err(format!(
+                "webhook host resolves to a disallowed address: {}",
+                addr.ip()
+            ));
+        }
+    }
+    Ok((parsed, pinned))
+}
+
+fn is_disallowed_target(ip: std::net::IpAddr) -> bool {
+    match ip {
+        std::net::IpAddr::V4(v4) => is_disallowed_v4(v4),
+        std::net::IpAddr::V6(v6) => {
+            // `::ffff:a.b.c.d` is a plain IPv4 address wearing a v6 suit —
+            // none of the v6-specific checks below catch e.g.
+            // `::ffff:169.254.169.254`, so unwrap it and re-run the v4 rules.
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_disallowed_v4(v4);
+            }
+            let segments = v6.segments();
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (segments[0] & 0xfe00) == 0xfc00 // unique local: fc00::/7
+                || (segments[0] & 0xffc0) == 0xfe80 // link-local: fe80::/10
+        }
+    }
+}
+
+fn is_disallowed_v4(v4: std::net::Ipv4Addr) -> bool {
+    v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified() || v4.is_multicast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn blocks_loopback_private_and_link_local_v4() {
+        assert!(is_disallowed_target(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1))));
+        assert!(is_disallowed_target(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))));
+        assert!(is_disallowed_target(IpAddr::V4(Ipv4Addr::new(169, 254, 169, 254))));
+    }
+
+    #[test]
+    fn blocks_ipv4_mapped_v6() {
+        // ::ffff:169.254.169.254 and ::ffff:127.0.0.1 are plain v4 addresses
+        // wearing a v6 suit; none of the v6-specific rules catch them on
+        // their own.
+        let mapped_metadata = Ipv4Addr::new(169, 254, 169, 254).to_ipv6_mapped();
+        assert!(is_disallowed_target(IpAddr::V6(mapped_metadata)));
+        let mapped_loopback = Ipv4Addr::new(127, 0, 0, 1).to_ipv6_mapped();
+        assert!(is_disallowed_target(IpAddr::V6(mapped_loopback)));
+    }
+
+    #[test]
+    fn allows_public_addresses() {
+        assert!(!is_disallowed_target(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8))));
+        let mapped_public = Ipv4Addr::new(8, 8, 8, 8).to_ipv6_mapped();
+        assert!(!is_disallowed_target(IpAddr::V6(mapped_public)));
+    }
+}
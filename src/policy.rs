@@ -0,0 +1,194 @@
+//! Script approval policy. Defaults to a built-in substring scan, but an
+//! operator can provide a `policy.lua` (`./policy.lua` in the working
+//! directory, or the file named by `POLICY_LUA_PATH`) to encode their own
+//! rules (allowed gcloud commands, required headers, project allow-lists)
+//! without recompiling.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+pub(crate) struct PolicyContext<'a> {
+    pub(crate) script_path: &'a str,
+    pub(crate) script_content: &'a str,
+    pub(crate) args: &'a [String],
+}
+
+pub(crate) struct PolicyDecision {
+    pub(crate) allowed: bool,
+    pub(crate) reason: String,
+}
+
+pub(crate) struct PolicyEngine {
+    lua_script: Option<String>,
+}
+
+const LUA_EVAL_BUDGET: Duration = Duration::from_secs(2);
+
+impl PolicyEngine {
+    /// Loads `policy.lua` from `path` if present; otherwise falls back to the
+    /// built-in default policy.
+    pub(crate) fn load(path: &Path) -> std::io::Result<Self> {
+        if path.exists() {
+            Ok(Self {
+                lua_script: Some(std::fs::read_to_string(path)?),
+            })
+        } else {
+            Ok(Self { lua_script: None })
+        }
+    }
+
+    pub(crate) fn evaluate(&self, ctx: &PolicyContext) -> PolicyDecision {
+        match &self.lua_script {
+            Some(script) => self.evaluate_lua(script, ctx).unwrap_or_else(|e| PolicyDecision {
+                allowed: false,
+                reason: format!("Policy evaluation error: {}", e),
+            }),
+            None => default_policy(ctx),
+        }
+    }
+
+    fn evaluate_lua(&self, script: &str, ctx: &PolicyContext) -> mlua::Result<PolicyDecision> {
+        // Only load the libraries a policy script needs to express allow/deny
+        // logic. `io`, `os`, and `package` (which can `require("os")` around
+        // the first two) are never loaded, so there's no live reference to
+        // shell or file access for a script to get at, unlike setting those
+        // globals to nil after the fact.
+        let libs = mlua::StdLib::TABLE | mlua::StdLib::STRING | mlua::StdLib::MATH;
+        let lua = mlua::Lua::new_with(libs, mlua::LuaOptions::default())?;
+
+        // `set_interrupt` is luau-only; on the lua54 backend we bound
+        // execution time with an instruction-count hook instead.
+        let deadline = Instant::now();
+        lua.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(10_000),
+            move |_, _| {
+                if deadline.elapsed() > LUA_EVAL_BUDGET {
+                    Err(mlua::Error::RuntimeError(
+                        "policy.lua exceeded its evaluation time budget".to_string(),
+                    ))
+                } else {
+                    Ok(())
+                }
+            },
+        );
+
+        lua.load(script).exec()?;
+        let allow_fn: mlua::Function = lua.globals().get("allow")?;
+
+        let ctx_table = lua.create_table()?;
+        ctx_table.set("script_path", ctx.script_path)?;
+        ctx_table.set("script_content", ctx.script_content)?;
+        ctx_table.set(
+            "args",
+            lua.create_sequence_from(ctx.args.iter().cloned())?,
+        )?;
+
+        let (allowed, reason): (bool, Option<String>) = allow_fn.call(ctx_table)?;
+        Ok(PolicyDecision {
+            allowed,
+            reason: reason.unwrap_or_else(|| {
+                if allowed {
+                    "allowed by policy.lua".to_string()
+                } else {
+                    "rejected by policy.lua".to_string()
+                }
+            }),
+        })
+    }
+}
+
+fn default_policy(ctx: &PolicyContext) -> PolicyDecision {
+    match scan_script_for_dangerous_patterns(ctx.script_content) {
+        Ok(()) => PolicyDecision {
+            allowed: true,
+            reason: "default policy: no dangerous patterns found".to_string(),
+        },
+        Err(reason) => PolicyDecision {
+            allowed: false,
+            reason,
+        },
+    }
+}
+
+fn scan_script_for_dangerous_patterns(script_content: &str) -> Result<(), String> {
+    let dangerous_patterns = vec![
+        ("rm -rf /", "Dangerous recursive delete"),
+        ("rm -rf /*", "Dangerous recursive delete"),
+        (":(){:|:&};:", "Fork bomb detected"),
+        ("curl.*\\|.*sh", "Piped execution from web"),
+        ("wget.*\\|.*sh", "Piped execution from web"),
+        ("dd if=/dev/", "Direct disk access"),
+        ("mkfs", "Filesystem creation"),
+        ("fdisk", "Disk partitioning"),
+        ("format", "Disk formatting"),
+        ("/etc/passwd", "Access to password file"),
+        ("sudo", "Privilege escalation"),
+        ("su ", "User switching"),
+        ("chmod 777", "Dangerous permission change"),
+        ("chown", "Ownership change"),
+    ];
+
+    for (pattern, description) in dangerous_patterns {
+        if script_content.contains(pattern) {
+            return Err(format!(
+                "Blocked dangerous pattern: {} ({})",
+                pattern, description
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> PolicyContext<'static> {
+        PolicyContext {
+            script_path: "deploy.sh",
+            script_content: "echo hi",
+            args: &[],
+        }
+    }
+
+    #[test]
+    fn lua_sandbox_has_no_os_library() {
+        let engine = PolicyEngine {
+            lua_script: Some(
+                "function allow(ctx) return pcall(function() return os.execute('true') end) end"
+                    .to_string(),
+            ),
+        };
+        let decision = engine.evaluate(&ctx());
+        assert!(!decision.allowed, "os.execute should be unreachable: {}", decision.reason);
+    }
+
+    #[test]
+    fn lua_sandbox_cannot_require_os() {
+        let engine = PolicyEngine {
+            lua_script: Some(
+                "function allow(ctx) return pcall(function() return require('os') end) end"
+                    .to_string(),
+            ),
+        };
+        let decision = engine.evaluate(&ctx());
+        assert!(!decision.allowed, "require('os') should be unreachable: {}", decision.reason);
+    }
+
+    #[test]
+    fn lua_sandbox_allows_table_string_math() {
+        let engine = PolicyEngine {
+            lua_script: Some(
+                "function allow(ctx) \
+                   local n = math.max(1, 2) \
+                   local s = string.upper('ok') \
+                   local t = {1, 2, 3} \
+                   return n == 2 and s == 'OK' and #t == 3 \
+                 end"
+                    .to_string(),
+            ),
+        };
+        let decision = engine.evaluate(&ctx());
+        assert!(decision.allowed, "allowed libraries should still work: {}", decision.reason);
+    }
+}
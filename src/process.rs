@@ -0,0 +1,19 @@
+//! Process-group lifecycle helper shared by the driver and the `runner`
+//! binary — both spawn a job's script in its own process group so this can
+//! reach everything it spawned, not just the immediate child.
+
+use std::time::Duration;
+
+/// Send SIGTERM to the job's whole process group, give it a grace period to
+/// exit, then SIGKILL anything still alive. `pid` doubles as the pgid since
+/// the child is spawned with `process_group(0)`.
+pub async fn terminate_process_group(pid: u32) {
+    let pgid = -(pid as i32);
+    unsafe {
+        libc::kill(pgid, libc::SIGTERM);
+    }
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    unsafe {
+        libc::kill(pgid, libc::SIGKILL);
+    }
+}
@@ -0,0 +1,42 @@
+//! Driver-side bookkeeping for the runner fleet: who's registered, what
+//! tags they advertise, and the FIFO of jobs waiting to be claimed. The
+//! built-in in-process executor claims jobs through this same queue, under
+//! the reserved `"local"` tag, so it's not a special case for callers.
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::time::Instant;
+
+/// A runner that has registered with the driver and may poll for work.
+pub(crate) struct RunnerRecord {
+    pub(crate) tags: Vec<String>,
+}
+
+/// A job waiting for an eligible runner to claim it. `required_tag` is the
+/// tag a caller asked for in `ScriptExecutionPayload::runner_tag`; `None`
+/// means any runner (including the built-in local one) may take it.
+pub(crate) struct QueuedJob {
+    pub(crate) job_id: String,
+    pub(crate) script_path: PathBuf,
+    pub(crate) args: Vec<String>,
+    pub(crate) required_tag: Option<String>,
+    /// When this job was enqueued, so a job stuck waiting for a tag nobody
+    /// services can still be reaped instead of sitting "running" forever.
+    pub(crate) queued_at: Instant,
+}
+
+pub(crate) const LOCAL_RUNNER_TAG: &str = "local";
+
+/// Pops the first queued job whose `required_tag` is satisfied by one of
+/// `available_tags`, if any. Not strictly FIFO across tags, but within a
+/// single tag jobs are still claimed in arrival order.
+pub(crate) fn claim_job(
+    pending: &mut VecDeque<QueuedJob>,
+    available_tags: &[String],
+) -> Option<QueuedJob> {
+    let pos = pending.iter().position(|job| match &job.required_tag {
+        None => true,
+        Some(tag) => available_tags.iter().any(|t| t == tag),
+    })?;
+    pending.remove(pos)
+}
@@ -0,0 +1,56 @@
+//! HTTP request/response bodies exchanged between the driver and a remote
+//! runner. Kept separate from the driver's own internal types (`JobLog`,
+//! `JobStatus`, ...) since a runner is a different binary, possibly a
+//! different host, and should only ever see this narrow contract.
+
+use serde::{Deserialize, Serialize};
+
+/// Sent once by a runner on startup (and again on reconnect) so the driver
+/// knows it exists and which capability tags it can claim jobs for.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RegisterRequest {
+    pub runner_id: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// A job handed to a runner in response to a poll. The script travels as
+/// its full source rather than a path, since the runner may be on a host
+/// that has never seen the driver's `allowed_scripts` directory.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PendingJobPayload {
+    pub job_id: String,
+    pub script_content: String,
+    pub args: Vec<String>,
+}
+
+/// Response to `GET /runners/:runner_id/poll`. `job` is `None` if nothing
+/// matched the runner's tags before the long-poll window elapsed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PollResponse {
+    pub job: Option<PendingJobPayload>,
+}
+
+/// One stdout/stderr line, reported as it's produced so the driver can keep
+/// forwarding it to `/logs/:job_id/stream` subscribers in real time.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutputLineRequest {
+    pub stream: String,
+    pub line: String,
+}
+
+/// Reported once a runner's script exits (or the runner gives up on it).
+/// `status` uses the same strings as `JobStatus::as_db_str`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct CompleteRequest {
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub error_message: Option<String>,
+}
+
+/// Response to `GET /runners/:runner_id/jobs/:job_id/cancelled`, polled by a
+/// runner while a job runs to learn whether the driver wants it stopped.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CancelledResponse {
+    pub cancelled: bool,
+}